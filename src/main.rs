@@ -15,6 +15,9 @@ const SYSTEM_OUT_MAX_LEN: usize = 65536;
 struct SuiteResults {
     passed: usize,
     failed: usize,
+    ignored: usize,
+    measured: usize,
+    filtered_out: usize,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -66,7 +69,11 @@ enum TestEvent {
     #[serde(rename = "started")]
     Started { name: String },
     #[serde(rename = "ok")]
-    Ok { name: String },
+    Ok {
+        name: String,
+        stdout: Option<String>,
+        stderr: Option<String>,
+    },
     #[serde(rename = "failed")]
     Failed {
         name: String,
@@ -74,7 +81,11 @@ enum TestEvent {
         stderr: Option<String>,
     },
     #[serde(rename = "ignored")]
-    Ignored { name: String },
+    Ignored {
+        name: String,
+        stdout: Option<String>,
+        stderr: Option<String>,
+    },
     #[serde(rename = "timeout")]
     Timeout { name: String },
 }
@@ -101,12 +112,20 @@ enum Event {
         duration: Option<f64>,
         exec_time: Option<f64>,
     },
+    #[serde(rename = "bench")]
+    Bench {
+        name: String,
+        median: f64,
+        deviation: f64,
+        mib_per_second: Option<f64>,
+    },
 }
 
 impl Event {
     fn get_duration(&self) -> Option<Duration> {
         match &self {
             Event::Suite { event: _ } => panic!(),
+            Event::Bench { .. } => panic!(),
             Event::TestStringTime {
                 event: _,
                 duration,
@@ -155,6 +174,67 @@ fn split_name(full_name: &str) -> (&str, String) {
     (name, module_path)
 }
 
+/// cargo interleaves human-readable banner lines between each suite's JSON
+/// stream, e.g. `Running unittests src/lib.rs (target/debug/deps/foo-abcd1234)`
+/// or `Doc-tests foo`. Return a label for the target they identify so the
+/// suite that follows can be named after it instead of an opaque index: the
+/// `Running` text before the parenthesised binary path (`unittests src/lib.rs`),
+/// or `Doc-tests <crate>` for a doctest run.
+fn parse_banner_line(line: &str) -> Option<String> {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix("Running ") {
+        let target = rest.split(" (").next().unwrap_or(rest).trim();
+        if target.is_empty() {
+            return None;
+        }
+        return Some(target.to_string());
+    }
+    if let Some(rest) = line.strip_prefix("Doc-tests ") {
+        let crate_name = rest.trim();
+        if crate_name.is_empty() {
+            return None;
+        }
+        return Some(format!("Doc-tests {}", crate_name));
+    }
+    None
+}
+
+/// Classname to attach to a test case: its module path when the test name
+/// has one, otherwise the suite's target name (useful for doctests, whose
+/// names carry no `::`-separated module path of their own).
+fn classname(module_path: &str, suite_target: Option<&str>) -> String {
+    if !module_path.is_empty() {
+        module_path.to_string()
+    } else {
+        suite_target.unwrap_or_default().to_string()
+    }
+}
+
+fn truncate(s: &str, max_len: usize) -> Cow<'_, str> {
+    if s.len() <= max_len {
+        return Cow::Borrowed(s);
+    }
+
+    let truncated_msg = "[...TRUNCATED...]";
+    if max_len > truncated_msg.len() {
+        let half_max_len = (max_len - truncated_msg.len()) / 2;
+        Cow::Owned(format!(
+            "{}\n{}\n{}",
+            s.split_at(half_max_len).0,
+            truncated_msg,
+            s.split_at(s.len() - half_max_len).1
+        ))
+    } else {
+        // max_len is too small to fit the marker alongside any content; just
+        // hard-cut to the limit instead of returning the whole string.
+        let mut cut = max_len.min(s.len());
+        while cut > 0 && !s.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        Cow::Borrowed(&s[..cut])
+    }
+}
+
 /// Attempt to populate failure with meaningful error messages
 /// If stderr is valid / non trivial, use that
 /// Otherwise attempt to extract error from stdout with regex
@@ -189,11 +269,17 @@ fn parse<T: BufRead>(
     let mut suite_index = 0;
     let mut current_suite_maybe: Option<TestSuite> = None;
     let mut tests: HashMap<String, TestCaseDetail> = HashMap::new();
+    let mut suite_case_count: usize = 0;
+    let mut pending_suite_name: Option<String> = None;
+    let mut current_suite_target: Option<String> = None;
 
     for line in input.lines() {
         let line = line?;
 
         if line.chars().find(|c| !c.is_whitespace()) != Some('{') {
+            if let Some(name) = parse_banner_line(&line) {
+                pending_suite_name = Some(name);
+            }
             continue;
         }
 
@@ -219,19 +305,72 @@ fn parse<T: BufRead>(
                 SuiteEvent::Started { test_count: _ } => {
                     assert!(current_suite_maybe.is_none());
                     assert!(tests.is_empty());
-                    let mut ts = TestSuite::new(&format!("{} #{}", suite_name_prefix, suite_index));
+                    current_suite_target = pending_suite_name.take();
+                    let name = current_suite_target
+                        .clone()
+                        .unwrap_or_else(|| format!("{} #{}", suite_name_prefix, suite_index));
+                    let mut ts = TestSuite::new(&name);
                     ts.set_timestamp(timestamp);
                     current_suite_maybe = Some(ts);
                     suite_index += 1;
+                    suite_case_count = 0;
                 }
-                SuiteEvent::Ok { results: _ } | SuiteEvent::Failed { results: _ } => {
+                SuiteEvent::Ok { results } | SuiteEvent::Failed { results } => {
                     assert_eq!(None, tests.iter().next());
-                    r.add_testsuite(
-                        current_suite_maybe.expect("Suite complete event found outside of suite!"),
-                    );
+
+                    let SuiteResults {
+                        passed,
+                        failed,
+                        ignored,
+                        measured,
+                        filtered_out,
+                    } = results;
+
+                    if suite_case_count != passed + failed {
+                        return Err(Error::new(
+                            ErrorKind::Other,
+                            format!(
+                                "suite reported {} passed + {} failed, but {} test cases were recorded",
+                                passed, failed, suite_case_count
+                            ),
+                        ));
+                    }
+
+                    let mut suite =
+                        current_suite_maybe.expect("Suite complete event found outside of suite!");
+                    suite.add_property("ignored", ignored.to_string());
+                    suite.add_property("measured", measured.to_string());
+                    suite.add_property("filtered_out", filtered_out.to_string());
+                    r.add_testsuite(suite);
                     current_suite_maybe = None;
+                    current_suite_target = None;
                 }
             },
+            Event::Bench {
+                name,
+                median,
+                deviation,
+                mib_per_second,
+            } => {
+                let mut current_suite = current_suite_maybe
+                    .take()
+                    .expect("Bench event found outside of suite!");
+
+                tests.remove(name).expect("Bench started event not found");
+
+                let (name, module_path) = split_name(name);
+                let mut tc = TestCase::success(&name, Duration::nanoseconds(*median as i64));
+                tc.set_classname(&classname(&module_path, current_suite_target.as_deref()));
+
+                let mut system_out = format!("deviation: {}", deviation);
+                if let Some(mib_per_second) = mib_per_second {
+                    system_out.push_str(&format!("\nthroughput: {} MiB/s", mib_per_second));
+                }
+                tc.set_system_out(&system_out);
+
+                current_suite.add_testcase(tc);
+                current_suite_maybe = Some(current_suite);
+            }
             Event::TestStringTime {
                 event,
                 duration: _,
@@ -259,14 +398,27 @@ fn parse<T: BufRead>(
                             )
                             .is_none());
                     }
-                    TestEvent::Ok { name } => {
+                    TestEvent::Ok {
+                        name,
+                        stdout,
+                        stderr,
+                    } => {
                         let now = Utc::now();
                         let detail = tests.remove(name).unwrap();
 
                         let (name, module_path) = split_name(&name);
                         let mut tc = TestCase::success(&name, duration);
-                        tc.set_classname(module_path.as_str());
+                        tc.set_classname(&classname(&module_path, current_suite_target.as_deref()));
+
+                        if let Some(stdout) = stdout {
+                            tc.set_system_out(&truncate(stdout, max_out_len));
+                        }
+                        if let Some(stderr) = stderr {
+                            tc.set_system_err(&truncate(stderr, max_out_len));
+                        }
+
                         current_suite.add_testcase(tc);
+                        suite_case_count += 1;
                     }
                     TestEvent::Failed {
                         name,
@@ -285,22 +437,7 @@ fn parse<T: BufRead>(
                             "cargo test",
                             &format!("failed {}::{}", module_path.as_str(), &name),
                         );
-                        failure.set_classname(module_path.as_str());
-
-                        fn truncate(s: &str, max_len: usize) -> Cow<'_, str> {
-                            if s.len() > max_len {
-                                let truncated_msg = "[...TRUNCATED...]";
-                                let half_max_len = (max_len - truncated_msg.len()) / 2;
-                                Cow::Owned(format!(
-                                    "{}\n{}\n{}",
-                                    s.split_at(half_max_len).0,
-                                    truncated_msg,
-                                    s.split_at(s.len() - half_max_len).1
-                                ))
-                            } else {
-                                Cow::Borrowed(s)
-                            }
-                        }
+                        failure.set_classname(&classname(&module_path, current_suite_target.as_deref()));
 
                         // if a error message can be guessed, use that
                         if let Some(message) = error_message {
@@ -316,10 +453,27 @@ fn parse<T: BufRead>(
                         }
 
                         current_suite.add_testcase(failure);
+                        suite_case_count += 1;
                     }
-                    TestEvent::Ignored { name } => {
+                    TestEvent::Ignored {
+                        name,
+                        stdout,
+                        stderr,
+                    } => {
                         assert!(tests.remove(name));
-                        current_suite.add_testcase(TestCase::skipped(name));
+
+                        let (name, module_path) = split_name(name);
+                        let mut tc = TestCase::skipped(&name);
+                        tc.set_classname(&classname(&module_path, current_suite_target.as_deref()));
+
+                        if let Some(stdout) = stdout {
+                            tc.set_system_out(&truncate(stdout, max_out_len));
+                        }
+                        if let Some(stderr) = stderr {
+                            tc.set_system_err(&truncate(stderr, max_out_len));
+                        }
+
+                        current_suite.add_testcase(tc);
                     }
                     TestEvent::Timeout { name: _ } => {
                         // An informative timeout event is emitted after a test has been running for
@@ -339,24 +493,120 @@ fn parse<T: BufRead>(
     Ok(r)
 }
 
-fn main() -> Result<()> {
-    let timestamp = OffsetDateTime::now_utc();
-    let stdin = std::io::stdin();
-    let stdin = stdin.lock();
+#[derive(Debug, PartialEq)]
+struct Args {
+    time_precision: DurationPrecision,
+    suite_prefix: String,
+    stdout_max_len: usize,
+    input: Option<String>,
+    output: Option<String>,
+}
 
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args> {
     // GitLab fails to parse the Junit XML if stdout is too long.
-    let max_out_len = match env::var("TEST_STDOUT_STDERR_MAX_LEN") {
-        Ok(val) => val
-            .parse::<usize>()
-            .expect("Failed to parse TEST_STDOUT_STDERR_MAX_LEN as a natural number"),
+    let mut stdout_max_len = match env::var("TEST_STDOUT_STDERR_MAX_LEN") {
+        Ok(val) => val.parse::<usize>().map_err(|_| {
+            Error::new(
+                ErrorKind::Other,
+                "Failed to parse TEST_STDOUT_STDERR_MAX_LEN as a natural number",
+            )
+        })?,
         Err(_) => SYSTEM_OUT_MAX_LEN,
     };
-    let report = parse(stdin, "cargo test", timestamp, max_out_len, DurationPrecision::MilliSeconds)?;
+    let mut time_precision = DurationPrecision::MilliSeconds;
+    let mut suite_prefix = "cargo test".to_string();
+    let mut input = None;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--time-precision" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| Error::new(ErrorKind::Other, "--time-precision requires a value"))?;
+                time_precision = match value.as_str() {
+                    "millis" => DurationPrecision::MilliSeconds,
+                    "seconds" => DurationPrecision::LiteralSeconds,
+                    other => {
+                        return Err(Error::new(
+                            ErrorKind::Other,
+                            format!(
+                                "Unknown --time-precision '{}', expected 'millis' or 'seconds'",
+                                other
+                            ),
+                        ))
+                    }
+                };
+            }
+            "--suite-prefix" => {
+                suite_prefix = args
+                    .next()
+                    .ok_or_else(|| Error::new(ErrorKind::Other, "--suite-prefix requires a value"))?;
+            }
+            "--stdout-max-len" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| Error::new(ErrorKind::Other, "--stdout-max-len requires a value"))?;
+                stdout_max_len = value.parse::<usize>().map_err(|_| {
+                    Error::new(
+                        ErrorKind::Other,
+                        "Failed to parse --stdout-max-len as a natural number",
+                    )
+                })?;
+            }
+            "--input" => {
+                input = Some(
+                    args.next()
+                        .ok_or_else(|| Error::new(ErrorKind::Other, "--input requires a value"))?,
+                );
+            }
+            "--output" => {
+                output = Some(
+                    args.next()
+                        .ok_or_else(|| Error::new(ErrorKind::Other, "--output requires a value"))?,
+                );
+            }
+            other => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("Unknown argument '{}'", other),
+                ))
+            }
+        }
+    }
+
+    Ok(Args {
+        time_precision,
+        suite_prefix,
+        stdout_max_len,
+        input,
+        output,
+    })
+}
+
+fn main() -> Result<()> {
+    let timestamp = OffsetDateTime::now_utc();
+    let args = parse_args(env::args().skip(1))?;
+
+    let input: Box<dyn Read> = match &args.input {
+        Some(path) => Box::new(std::fs::File::open(path)?),
+        None => Box::new(std::io::stdin()),
+    };
 
-    let stdout = std::io::stdout();
-    let stdout = stdout.lock();
+    let report = parse(
+        BufReader::new(input),
+        &args.suite_prefix,
+        timestamp,
+        args.stdout_max_len,
+        args.time_precision,
+    )?;
+
+    let output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    };
     report
-        .write_xml(stdout)
+        .write_xml(output)
         .map_err(|e| Error::new(ErrorKind::Other, format!("{:#}", e)))?;
     Ok(())
 }
@@ -368,7 +618,9 @@ mod tests {
     use junit_report::*;
     use regex::Regex;
 
-    use crate::{DurationPrecision, parse};
+    use std::env;
+
+    use crate::{parse, parse_args, DurationPrecision};
 
     use super::SYSTEM_OUT_MAX_LEN;
 
@@ -417,6 +669,16 @@ mod tests {
         assert!(parse_string("{garbage}", SYSTEM_OUT_MAX_LEN).is_err());
     }
 
+    #[test]
+    fn error_on_suite_count_mismatch() {
+        let input = concat!(
+            "{ \"type\": \"suite\", \"event\": \"started\", \"test_count\": 1 }\n",
+            "{ \"type\": \"suite\", \"event\": \"ok\", \"passed\": 1, \"failed\": 0, ",
+            "\"ignored\": 0, \"measured\": 0, \"filtered_out\": 0 }\n",
+        );
+        assert!(parse_string(input, SYSTEM_OUT_MAX_LEN).is_err());
+    }
+
     #[test]
     fn success_self() {
         let report = parse_bytes_milli(include_bytes!("test_inputs/self.json"), SYSTEM_OUT_MAX_LEN)
@@ -559,6 +821,28 @@ mod tests {
         assert_output(&report, include_bytes!("expected_outputs/azfunc.out"));
     }
 
+    #[test]
+    fn bench_events() {
+        let report = parse_bytes(include_bytes!("test_inputs/bench.json"), SYSTEM_OUT_MAX_LEN)
+            .expect("Could not parse test input");
+        let suite = &report.testsuites()[0];
+        let test_cases = suite.testcases();
+
+        assert_eq!(test_cases[0].name(), "bench_with_throughput");
+        assert_eq!(test_cases[0].time(), &Duration::nanoseconds(12345));
+        assert!(test_cases[0].is_success());
+        let system_out = test_cases[0].system_out().as_ref().unwrap();
+        assert!(system_out.contains("deviation: 678"));
+        assert!(system_out.contains("throughput: 512.5 MiB/s"));
+
+        assert_eq!(test_cases[1].name(), "bench_no_throughput");
+        assert_eq!(test_cases[1].time(), &Duration::nanoseconds(9999));
+        assert!(test_cases[1].is_success());
+        let system_out = test_cases[1].system_out().as_ref().unwrap();
+        assert!(system_out.contains("deviation: 111"));
+        assert!(!system_out.contains("throughput"));
+    }
+
     #[test]
     fn float_time() {
         parse_bytes(
@@ -567,4 +851,125 @@ mod tests {
         )
         .expect("Could not parse test input");
     }
+
+    #[test]
+    fn truncate_hard_cuts_when_limit_is_smaller_than_the_marker() {
+        let out = crate::truncate("hello world", 5);
+        assert_eq!(out, "hello");
+        assert!(out.len() <= 5);
+    }
+
+    #[test]
+    fn parse_banner_line_running_target() {
+        assert_eq!(
+            crate::parse_banner_line(
+                "   Running unittests src/lib.rs (target/debug/deps/foo-abcd1234)"
+            ),
+            Some("unittests src/lib.rs".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_banner_line_doc_tests() {
+        assert_eq!(
+            crate::parse_banner_line("     Doc-tests foo"),
+            Some("Doc-tests foo".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_banner_line_ignores_non_banner_lines() {
+        assert_eq!(crate::parse_banner_line(""), None);
+        assert_eq!(crate::parse_banner_line("running out of time"), None);
+        assert_eq!(
+            crate::parse_banner_line("test result: ok. 1 passed; 0 failed;"),
+            None
+        );
+    }
+
+    #[test]
+    fn suite_names_from_banner_lines() {
+        let report = parse_bytes(
+            include_bytes!("test_inputs/banner_suite_name.json"),
+            SYSTEM_OUT_MAX_LEN,
+        )
+        .expect("Could not parse test input");
+        let suites = report.testsuites();
+        assert_eq!(suites[0].name(), "unittests src/lib.rs");
+        assert_eq!(suites[1].name(), "Doc-tests foo");
+    }
+
+    #[test]
+    fn parse_args_defaults() {
+        env::remove_var("TEST_STDOUT_STDERR_MAX_LEN");
+        let args = parse_args(std::iter::empty::<String>()).expect("defaults should parse");
+        assert_eq!(args.time_precision, DurationPrecision::MilliSeconds);
+        assert_eq!(args.suite_prefix, "cargo test");
+        assert_eq!(args.stdout_max_len, SYSTEM_OUT_MAX_LEN);
+        assert_eq!(args.input, None);
+        assert_eq!(args.output, None);
+    }
+
+    #[test]
+    fn parse_args_flags_override_defaults() {
+        env::remove_var("TEST_STDOUT_STDERR_MAX_LEN");
+        let argv = [
+            "--time-precision",
+            "seconds",
+            "--suite-prefix",
+            "my suite",
+            "--stdout-max-len",
+            "128",
+            "--input",
+            "in.json",
+            "--output",
+            "out.xml",
+        ]
+        .iter()
+        .map(|s| s.to_string());
+        let args = parse_args(argv).expect("flags should parse");
+        assert_eq!(args.time_precision, DurationPrecision::LiteralSeconds);
+        assert_eq!(args.suite_prefix, "my suite");
+        assert_eq!(args.stdout_max_len, 128);
+        assert_eq!(args.input, Some("in.json".to_string()));
+        assert_eq!(args.output, Some("out.xml".to_string()));
+    }
+
+    #[test]
+    fn parse_args_flag_overrides_env_var() {
+        env::set_var("TEST_STDOUT_STDERR_MAX_LEN", "999");
+        let argv = ["--stdout-max-len", "42"].iter().map(|s| s.to_string());
+        let args = parse_args(argv).expect("flag should win over env var");
+        assert_eq!(args.stdout_max_len, 42);
+        env::remove_var("TEST_STDOUT_STDERR_MAX_LEN");
+    }
+
+    #[test]
+    fn parse_args_uses_env_var_when_flag_absent() {
+        env::set_var("TEST_STDOUT_STDERR_MAX_LEN", "999");
+        let args =
+            parse_args(std::iter::empty::<String>()).expect("env var fallback should parse");
+        assert_eq!(args.stdout_max_len, 999);
+        env::remove_var("TEST_STDOUT_STDERR_MAX_LEN");
+    }
+
+    #[test]
+    fn parse_args_rejects_unknown_flag() {
+        let argv = ["--bogus"].iter().map(|s| s.to_string());
+        assert!(parse_args(argv).is_err());
+    }
+
+    #[test]
+    fn parse_args_rejects_missing_value() {
+        let argv = ["--suite-prefix"].iter().map(|s| s.to_string());
+        assert!(parse_args(argv).is_err());
+    }
+
+    #[test]
+    fn parse_args_rejects_unknown_time_precision() {
+        let argv = ["--time-precision", "fortnights"]
+            .iter()
+            .map(|s| s.to_string());
+        assert!(parse_args(argv).is_err());
+    }
 }